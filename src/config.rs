@@ -0,0 +1,223 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+#[derive(Debug, Deserialize, Default)]
+pub struct Config {
+    #[serde(default)]
+    pub paths_to_watch: Vec<PathBuf>,
+}
+
+/// A single yaml file contributing to the effective `Config`.
+///
+/// A `required` fragment that is missing is a hard error (the file the
+/// user explicitly named with `--config`); a missing optional one is
+/// skipped, so a directory of drop-in fragments doesn't break just because
+/// one package didn't install its piece. Any other read or parse failure
+/// is an error for both kinds.
+struct ConfigFragment {
+    path: PathBuf,
+    required: bool,
+}
+
+/// Loads the effective `Config` from `input`, which may be a single yaml
+/// file or a directory of `*.yaml` fragments merged in sorted filename
+/// order (see [`load_fragment_dir`]).
+pub fn load(input: &Path) -> anyhow::Result<Config> {
+    if input.is_dir() {
+        load_fragment_dir(input)
+    } else {
+        let fragment = ConfigFragment {
+            path: input.to_path_buf(),
+            required: true,
+        };
+        // `load_fragment` never returns `Ok(None)` for a required fragment --
+        // a missing one is an `Err` straight out of the `NotFound` arm below.
+        Ok(load_fragment(&fragment)?.expect("required fragment always yields a Config or an error"))
+    }
+}
+
+/// Reads every `*.yaml` file directly under `dir` in sorted filename order
+/// and merges them into one `Config`, concatenating and de-duplicating
+/// `paths_to_watch`. Fragments are optional: one that disappears between
+/// the directory listing and the read is logged and skipped rather than
+/// failing the whole load.
+fn load_fragment_dir(dir: &Path) -> anyhow::Result<Config> {
+    let mut fragment_paths: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("reading config fragment directory {}", dir.display()))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "yaml"))
+        .collect();
+    fragment_paths.sort();
+
+    let mut merged = Config::default();
+    for path in fragment_paths {
+        let fragment = ConfigFragment {
+            path,
+            required: false,
+        };
+        if let Some(piece) = load_fragment(&fragment)? {
+            merged.paths_to_watch.extend(piece.paths_to_watch);
+        }
+    }
+    dedup_paths(&mut merged.paths_to_watch);
+    Ok(merged)
+}
+
+fn load_fragment(fragment: &ConfigFragment) -> anyhow::Result<Option<Config>> {
+    match fs::read_to_string(&fragment.path) {
+        Ok(content) => {
+            let config: Config = serde_yaml::from_str(&content)
+                .with_context(|| format!("parsing {}", fragment.path.display()))?;
+            Ok(Some(config))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            if fragment.required {
+                Err(anyhow!("config file {} not found", fragment.path.display()))
+            } else {
+                debug!(
+                    "Optional config fragment {} not found, skipping",
+                    fragment.path.display()
+                );
+                Ok(None)
+            }
+        }
+        Err(e) => Err(e).with_context(|| format!("reading {}", fragment.path.display())),
+    }
+}
+
+fn dedup_paths(paths: &mut Vec<PathBuf>) {
+    let mut seen = HashSet::new();
+    paths.retain(|path| seen.insert(path.clone()));
+}
+
+/// Expands each `paths_to_watch` entry as a glob pattern (so `~` and
+/// `*`/`**` wildcards resolve to concrete directories, e.g.
+/// `~/scratch/**/tmp` or `/build/*/artifacts`) before the walker ever sees
+/// it, and de-duplicates overlapping matches. An entry with no wildcard
+/// characters is passed through unchanged (after `~` expansion) whether or
+/// not it currently exists, matching the old literal-path behavior -- a
+/// root that doesn't exist yet still needs to reach `WalkDir`/`notify` so
+/// the existing "no such file" error gets logged. An entry containing `*`,
+/// `?` or `[` is always treated as a pattern, so a directory literally
+/// named `foo[1]` is matched as `foo` followed by the character class
+/// `[1]` (i.e. `foo1`), not as itself.
+pub fn expand_watch_paths(config: Config) -> anyhow::Result<Config> {
+    let mut expanded = Vec::new();
+    for pattern in &config.paths_to_watch {
+        let pattern = expand_tilde(pattern);
+        let pattern_str = pattern
+            .to_str()
+            .ok_or_else(|| anyhow!("non-utf8 path pattern: {}", pattern.display()))?;
+
+        if !has_glob_metacharacters(pattern_str) {
+            expanded.push(pattern);
+            continue;
+        }
+
+        let matches = glob::glob(pattern_str)
+            .with_context(|| format!("invalid glob pattern {pattern_str}"))?;
+        for entry in matches {
+            match entry {
+                Ok(path) => expanded.push(path),
+                Err(e) => warn!("Error resolving glob entry in {pattern_str}: {e}"),
+            }
+        }
+    }
+    dedup_paths(&mut expanded);
+    Ok(Config {
+        paths_to_watch: expanded,
+    })
+}
+
+/// Whether `pattern` uses any syntax the `glob` crate treats specially
+/// (`?`, `*`/`**`, `[...]`/`[!...]`); `glob` has no brace-expansion support,
+/// so `{` is not a metacharacter here.
+fn has_glob_metacharacters(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+/// Replaces a leading `~` path component with the user's home directory.
+fn expand_tilde(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn load_merges_fragments_in_sorted_order_and_dedupes() -> anyhow::Result<()> {
+        let dir = TempDir::new()?;
+        fs::write(
+            dir.path().join("10-base.yaml"),
+            "paths_to_watch:\n  - /build/shared\n",
+        )?;
+        fs::write(
+            dir.path().join("20-extra.yaml"),
+            "paths_to_watch:\n  - /build/extra\n  - /build/shared\n",
+        )?;
+
+        let config = load(dir.path())?;
+
+        assert_eq!(
+            config.paths_to_watch,
+            vec![PathBuf::from("/build/shared"), PathBuf::from("/build/extra")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn load_missing_single_file_is_an_error() {
+        let missing = PathBuf::from("/nonexistent/dir_ttl.yaml");
+        let err = load(&missing).unwrap_err();
+        assert!(
+            err.to_string().contains("config file") && err.to_string().contains("not found"),
+            "expected a 'config file ... not found' error, got: {err}"
+        );
+    }
+
+    #[test]
+    fn expand_watch_paths_resolves_globs_and_dedupes() -> anyhow::Result<()> {
+        let root = TempDir::new()?;
+        fs::create_dir(root.path().join("build-a"))?;
+        fs::create_dir(root.path().join("build-b"))?;
+
+        let config = Config {
+            paths_to_watch: vec![
+                root.path().join("build-*"),
+                root.path().join("build-a"),
+            ],
+        };
+
+        let expanded = expand_watch_paths(config)?;
+
+        assert_eq!(
+            expanded.paths_to_watch,
+            vec![root.path().join("build-a"), root.path().join("build-b")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn expand_watch_paths_keeps_nonexistent_literal_path() -> anyhow::Result<()> {
+        let missing = PathBuf::from("/nonexistent/dir_ttl_root");
+        let config = Config {
+            paths_to_watch: vec![missing.clone()],
+        };
+
+        let expanded = expand_watch_paths(config)?;
+
+        assert_eq!(expanded.paths_to_watch, vec![missing]);
+        Ok(())
+    }
+}
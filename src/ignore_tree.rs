@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use tracing::{debug, warn};
+
+/// Name of the per-directory ignore file, gitignore-style, that exempts a
+/// matching directory from TTL deletion even once it has expired.
+pub const IGNORE_FILE_NAME: &str = ".dir_ttl_ignore";
+
+/// A compiled ignore file plus the mtime it was compiled from (`None` if no
+/// ignore file existed at the time), so [`IgnoreTree::compiled_for`] can
+/// tell a still-fresh cache entry from one an edit has invalidated.
+struct CachedIgnore {
+    gitignore: Option<Gitignore>,
+    mtime: Option<SystemTime>,
+}
+
+/// A cache of compiled `.dir_ttl_ignore` files, keyed by the directory that
+/// contains them. A cache entry is only reused while the ignore file's mtime
+/// (or absence) matches what it was compiled from, so a long-lived
+/// `--watch` process picks up an ignore file that's added, edited, or
+/// removed while it runs instead of being stuck with whatever was there the
+/// first time the directory was visited.
+#[derive(Default)]
+pub struct IgnoreTree {
+    cache: HashMap<PathBuf, CachedIgnore>,
+}
+
+impl IgnoreTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if `path` is protected from deletion by a
+    /// `.dir_ttl_ignore` file somewhere between `root` and `path`'s parent.
+    /// Nearer ignore files take precedence over farther ones, and a
+    /// negated pattern (`!pattern`) re-includes a path an ancestor ignored,
+    /// matching standard gitignore stacking.
+    pub fn is_ignored(&mut self, root: &Path, path: &Path) -> bool {
+        let mut ignored = false;
+        for dir in ancestors_from_root(root, path) {
+            if let Some(gitignore) = self.compiled_for(&dir) {
+                match gitignore.matched(path, true) {
+                    ignore::Match::Ignore(_) => ignored = true,
+                    ignore::Match::Whitelist(_) => ignored = false,
+                    ignore::Match::None => {}
+                }
+            }
+        }
+        ignored
+    }
+
+    fn compiled_for(&mut self, dir: &Path) -> Option<Gitignore> {
+        let current_mtime = ignore_file_mtime(dir);
+        let is_fresh = self
+            .cache
+            .get(dir)
+            .is_some_and(|cached| cached.mtime == current_mtime);
+
+        if !is_fresh {
+            self.cache.insert(
+                dir.to_path_buf(),
+                CachedIgnore {
+                    gitignore: compile_ignore_file(dir),
+                    mtime: current_mtime,
+                },
+            );
+        }
+        self.cache.get(dir).and_then(|cached| cached.gitignore.clone())
+    }
+}
+
+fn ignore_file_mtime(dir: &Path) -> Option<SystemTime> {
+    std::fs::metadata(dir.join(IGNORE_FILE_NAME))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn compile_ignore_file(dir: &Path) -> Option<Gitignore> {
+    let ignore_file = dir.join(IGNORE_FILE_NAME);
+    if !ignore_file.is_file() {
+        return None;
+    }
+
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(e) = builder.add(&ignore_file) {
+        warn!("Failed to parse {}: {}", ignore_file.display(), e);
+        return None;
+    }
+    match builder.build() {
+        Ok(gitignore) => {
+            debug!("Compiled ignore rules from {}", ignore_file.display());
+            Some(gitignore)
+        }
+        Err(e) => {
+            warn!("Failed to compile {}: {}", ignore_file.display(), e);
+            None
+        }
+    }
+}
+
+/// Directories from `root` down to (and including) `path`'s parent, in
+/// top-down order, so callers can apply rules nearest-wins. Empty when
+/// `path` is `root` itself (no parent under `root` to collect rules from)
+/// rather than walking past `root` up to the filesystem root -- `path`'s
+/// parent is then one level *above* `root`, which would otherwise never
+/// satisfy the `dir == root` stop condition.
+fn ancestors_from_root(root: &Path, path: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let mut current = path.parent();
+    while let Some(dir) = current {
+        if !dir.starts_with(root) {
+            break;
+        }
+        dirs.push(dir.to_path_buf());
+        if dir == root {
+            break;
+        }
+        current = dir.parent();
+    }
+    dirs.reverse();
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn ignores_matching_directory_under_root() -> anyhow::Result<()> {
+        let root = TempDir::new()?;
+        fs::write(root.path().join(IGNORE_FILE_NAME), "protected*\n")?;
+        fs::create_dir(root.path().join("protected_build"))?;
+        fs::create_dir(root.path().join("ttl=1min"))?;
+
+        let mut tree = IgnoreTree::new();
+        assert!(tree.is_ignored(root.path(), &root.path().join("protected_build")));
+        assert!(!tree.is_ignored(root.path(), &root.path().join("ttl=1min")));
+        Ok(())
+    }
+
+    #[test]
+    fn nested_ignore_file_can_negate_ancestor_rule() -> anyhow::Result<()> {
+        let root = TempDir::new()?;
+        fs::write(root.path().join(IGNORE_FILE_NAME), "*\n")?;
+        let subdir = root.path().join("sub");
+        fs::create_dir(&subdir)?;
+        fs::write(subdir.join(IGNORE_FILE_NAME), "!keep_me\n")?;
+        fs::create_dir(subdir.join("keep_me"))?;
+
+        let mut tree = IgnoreTree::new();
+        assert!(!tree.is_ignored(root.path(), &subdir.join("keep_me")));
+        Ok(())
+    }
+
+    #[test]
+    fn does_not_walk_past_root_when_path_is_root_itself() {
+        // WalkDir's depth-0 entry for a watched root is the root itself, so
+        // `is_ignored` gets called with `path == root`. That must not climb
+        // above `root` and pick up an unrelated ancestor's ignore file.
+        let root = PathBuf::from("/tmp/some/watched-root");
+        assert_eq!(ancestors_from_root(&root, &root), Vec::<PathBuf>::new());
+    }
+
+    #[test]
+    fn picks_up_ignore_file_added_or_edited_after_first_lookup() -> anyhow::Result<()> {
+        let root = TempDir::new()?;
+        fs::create_dir(root.path().join("build"))?;
+        let mut tree = IgnoreTree::new();
+
+        // No ignore file yet: not protected, and the miss gets cached.
+        assert!(!tree.is_ignored(root.path(), &root.path().join("build")));
+
+        // A long-lived --watch process then sees the file appear...
+        fs::write(root.path().join(IGNORE_FILE_NAME), "build\n")?;
+        assert!(tree.is_ignored(root.path(), &root.path().join("build")));
+
+        // ...and later sees it edited to stop protecting the directory,
+        // without needing a process restart to notice either change.
+        fs::write(root.path().join(IGNORE_FILE_NAME), "nothing_here\n")?;
+        assert!(!tree.is_ignored(root.path(), &root.path().join("build")));
+        Ok(())
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::bail;
+use tracing::field::{Field, Visit};
+use tracing::{debug, warn, Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{filter::LevelFilter, Layer};
+
+/// How long a session log file is kept around before [`init`] prunes it on
+/// a later run. The tool is typically invoked repeatedly (cron, or a
+/// long-lived `--watch` process), so without this the session directory
+/// would grow log files forever.
+const LOG_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Directory under the OS temp dir where per-session log files are kept.
+/// Scoped by uid (rather than shared by every user on the box) so the path
+/// isn't something another local user could pre-create and point a
+/// symlink through; see [`ensure_private_dir`] for the rest of the
+/// hardening around it.
+fn session_log_dir() -> PathBuf {
+    std::env::temp_dir()
+        .join("dir_ttl")
+        .join(format!("sessions-{}", current_uid()))
+}
+
+/// Effective user id of the running process. Declared directly via FFI
+/// rather than pulling in a dependency for a single libc call already
+/// linked into every unix binary.
+fn current_uid() -> u32 {
+    extern "C" {
+        fn geteuid() -> u32;
+    }
+    unsafe { geteuid() }
+}
+
+/// `O_NOFOLLOW`'s raw value on Linux, used the same way as [`current_uid`]'s
+/// raw `geteuid` call rather than pulling in a dependency for one flag: it
+/// makes the session log file open fail instead of silently following a
+/// symlink planted after [`ensure_private_file`]'s check but before the open.
+const O_NOFOLLOW: i32 = 0x20000;
+
+/// Creates `dir` as a directory private to the current user (mode `0700`),
+/// refusing to trust a path that's already something else: a symlink
+/// (which would let another local user redirect our reads/writes
+/// elsewhere) or a directory owned by a different uid (which another user
+/// could have pre-created at this exact, predictable path before we ever
+/// ran). Without this check, a multi-user host running this tool
+/// unattended via cron -- plausibly as root -- would read from and append
+/// tracing output to whatever another local user planted at the expected
+/// session path.
+fn ensure_private_dir(dir: &Path) -> anyhow::Result<()> {
+    match fs::symlink_metadata(dir) {
+        Ok(meta) if meta.file_type().is_symlink() => {
+            bail!("refusing to use {}: it is a symlink", dir.display());
+        }
+        Ok(meta) if meta.uid() != current_uid() => {
+            bail!(
+                "refusing to use {}: owned by a different user",
+                dir.display()
+            );
+        }
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            fs::create_dir_all(dir)?;
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    // `create_dir_all` follows symlinks and doesn't error if something was
+    // raced into place at `dir` between the check above and here, so
+    // re-verify immediately before trusting the path enough to chmod it --
+    // `set_permissions` follows symlinks too.
+    check_not_symlink_or_foreign(dir)?;
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    Ok(())
+}
+
+/// Same ownership/symlink check as [`ensure_private_dir`], applied to the
+/// session log file itself before it's read from or appended to. A file
+/// that doesn't exist yet is fine -- `init` is about to create one -- but
+/// any other stat failure is surfaced rather than silently treated as safe
+/// to proceed past.
+fn ensure_private_file(path: &Path) -> anyhow::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(_) => check_not_symlink_or_foreign(path),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Bails if `path` is a symlink or owned by a different uid than this
+/// process.
+fn check_not_symlink_or_foreign(path: &Path) -> anyhow::Result<()> {
+    let meta = fs::symlink_metadata(path)?;
+    if meta.file_type().is_symlink() {
+        bail!("refusing to use {}: it is a symlink", path.display());
+    }
+    if meta.uid() != current_uid() {
+        bail!("refusing to use {}: owned by a different user", path.display());
+    }
+    Ok(())
+}
+
+/// A stable id shared by every invocation started on the same calendar
+/// day, so that the repeated cron-style invocations that are this tool's
+/// main usage pattern all append to, and dedup stderr against, the same
+/// log file instead of each getting its own empty one.
+fn session_id() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / (24 * 60 * 60)
+}
+
+/// Sets up tracing so that every event, including debug, is written in
+/// full to a per-session log file, while only warnings and errors reach
+/// stderr -- and a given warning/error line is only shown on stderr once
+/// per session, however many times it recurs across invocations (e.g. the
+/// same unreadable path hit on every cron-triggered sweep). Returns the
+/// path of the session's log file so the caller can log where it is.
+pub fn init() -> anyhow::Result<PathBuf> {
+    let dir = session_log_dir();
+    ensure_private_dir(&dir)?;
+    prune_old_logs(&dir);
+    let log_path = dir.join(format!("{}.log", session_id()));
+    ensure_private_file(&log_path)?;
+
+    // Read before opening for append, so stderr lines already shown by an
+    // earlier invocation this session stay suppressed.
+    let already_logged = fs::read_to_string(&log_path).unwrap_or_default();
+
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .custom_flags(O_NOFOLLOW)
+        .open(&log_path)?;
+
+    let file_layer = tracing_subscriber::fmt::layer()
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .with_filter(LevelFilter::DEBUG);
+
+    tracing_subscriber::registry()
+        .with(file_layer)
+        .with(DedupStderrLayer::new(already_logged))
+        .init();
+
+    // Second pruning pass, now that the subscriber is installed, so its log
+    // events are recorded; the pass above runs before any subscriber exists
+    // and its log events are dropped. Today's log file is never stale
+    // enough to be pruned.
+    prune_old_logs(&dir);
+
+    Ok(log_path)
+}
+
+/// Deletes session log files older than [`LOG_RETENTION`], best-effort: a
+/// file another process is still writing to, or that disappears mid-scan,
+/// is logged and skipped rather than failing the whole sweep.
+fn prune_old_logs(dir: &std::path::Path) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("Failed to list session log directory {}: {}", dir.display(), e);
+            return;
+        }
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "log") {
+            continue;
+        }
+
+        let is_stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| {
+                modified
+                    .elapsed()
+                    .is_ok_and(|age| age > LOG_RETENTION)
+            });
+        if !is_stale {
+            continue;
+        }
+
+        match fs::remove_file(&path) {
+            Ok(()) => debug!("Pruned stale session log {}", path.display()),
+            Err(e) => warn!("Failed to prune session log {}: {}", path.display(), e),
+        }
+    }
+}
+
+/// Writes warnings and errors to stderr, but only the first time a given
+/// formatted line is seen this session -- repeat occurrences, whether
+/// within this process or a prior invocation that logged to the same
+/// session file, are still recorded in full by [`init`]'s file layer.
+struct DedupStderrLayer {
+    /// Content of the session log file as of process start, used to
+    /// suppress lines an earlier invocation already showed the user.
+    already_logged: String,
+    seen: Mutex<HashSet<String>>,
+}
+
+impl DedupStderrLayer {
+    fn new(already_logged: String) -> Self {
+        Self {
+            already_logged,
+            seen: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// True if `line` hasn't been shown yet: it isn't in the log content
+    /// carried over from an earlier invocation this session, and this is
+    /// the first time this process itself has seen it. Split out from
+    /// `on_event` so the dedup logic can be tested without going through
+    /// `tracing`'s subscriber machinery.
+    fn should_emit(&self, line: &str) -> bool {
+        if self.already_logged.contains(line) {
+            return false;
+        }
+        self.seen.lock().unwrap().insert(line.to_string())
+    }
+}
+
+impl<S: Subscriber> Layer<S> for DedupStderrLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if !matches!(*event.metadata().level(), Level::WARN | Level::ERROR) {
+            return;
+        }
+
+        let mut message = MessageVisitor::default();
+        event.record(&mut message);
+        let line = format!(
+            "{} {}: {}",
+            event.metadata().level(),
+            event.metadata().target(),
+            message.0
+        );
+
+        if self.should_emit(&line) {
+            eprintln!("{line}");
+        }
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{value:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+    use tempfile::TempDir;
+
+    #[test]
+    fn line_already_in_the_session_log_is_suppressed() {
+        let line = "WARN dir_ttl: disk is getting full";
+        let layer = DedupStderrLayer::new(line.to_string());
+
+        // Seeded via `already_logged` (a prior invocation already showed
+        // this line), so it must never be emitted again this session.
+        assert!(!layer.should_emit(line));
+    }
+
+    #[test]
+    fn fresh_line_is_shown_once_then_suppressed_within_the_same_process() {
+        let layer = DedupStderrLayer::new(String::new());
+        let line = "WARN dir_ttl: first time seeing this";
+
+        assert!(layer.should_emit(line));
+        assert!(!layer.should_emit(line));
+    }
+
+    #[test]
+    fn prune_old_logs_deletes_stale_files_but_keeps_fresh_ones() -> anyhow::Result<()> {
+        let dir = TempDir::new()?;
+        let stale = dir.path().join("19000.log");
+        let fresh = dir.path().join("19934.log");
+        fs::write(&stale, "old session")?;
+        fs::write(&fresh, "current session")?;
+
+        let old_mtime = SystemTime::now() - LOG_RETENTION - Duration::from_secs(60);
+        set_mtime(&stale, old_mtime)?;
+
+        prune_old_logs(dir.path());
+
+        assert!(!stale.exists(), "stale log should have been pruned");
+        assert!(fresh.exists(), "fresh log should have been kept");
+        Ok(())
+    }
+
+    fn set_mtime(path: &Path, mtime: SystemTime) -> anyhow::Result<()> {
+        let file = fs::File::open(path)?;
+        file.set_modified(mtime)?;
+        Ok(())
+    }
+}
@@ -1,55 +1,17 @@
-use anyhow::anyhow;
 use clap::Parser;
-use regex::Regex;
-use serde::Deserialize;
-use std::fs;
 use std::path::PathBuf;
-use std::time::SystemTime;
+use std::time::Duration;
 use tracing::{debug, error, info};
-use walkdir::WalkDir;
-
-fn process_directory(entry: &walkdir::DirEntry, re: &Regex) -> anyhow::Result<()> {
-    let dir_name = entry
-        .path()
-        .file_name()
-        .ok_or(anyhow!("No file name"))?
-        .to_str()
-        .ok_or(anyhow!("No str"))?;
-
-    if let Some(captures) = re.captures(dir_name) {
-        if let (Some(ttl_value), Some(ttl_unit)) = (captures.get(1), captures.get(2)) {
-            let ttl_value = ttl_value.as_str().parse::<u64>()?;
-            let ttl_seconds = match ttl_unit.as_str() {
-                "min" => ttl_value * 60,
-                "d" => ttl_value * 24 * 60 * 60,
-                "m" => ttl_value * 30 * 24 * 60 * 60, // Approximate
-                "y" => ttl_value * 365 * 24 * 60 * 60, // Approximate
-                _ => return Ok(()),                   // Skip if unit is not recognized
-            };
-
-            let metadata = fs::metadata(entry.path())?;
-            let creation_time = metadata.created()?;
-            let current_time = SystemTime::now();
-
-            if let Ok(duration) = current_time.duration_since(creation_time) {
-                if duration.as_secs() > ttl_seconds {
-                    info!("Deleting directory: {}", entry.path().display());
-                    fs::remove_dir_all(entry.path())?;
-                } else {
-                    debug!("Directory {} not yet expired", entry.path().display());
-                }
-            }
-        }
-    } else {
-        debug!("Directory {dir_name} does not match TTL pattern");
-    }
-    Ok(())
-}
 
-#[derive(Debug, Deserialize)]
-struct Config {
-    paths_to_watch: Vec<PathBuf>,
-}
+use config::Config;
+use ignore_tree::IgnoreTree;
+use ttl::{process_directory, DryRunSummary, TimeSource};
+
+mod config;
+mod ignore_tree;
+mod logging;
+mod ttl;
+mod watch;
 
 #[derive(Parser, Debug)]
 #[command(
@@ -57,50 +19,71 @@ struct Config {
     about = "A tool to clean up directories based on ttl described by directories"
 )]
 struct Cli {
-    /// Path to the yaml configuration file
+    /// Path to the yaml configuration file, or a directory of `*.yaml`
+    /// fragments to merge (see `config::load`)
     #[arg(
         short,
         long,
-        value_name = "FILE",
-        help = "Specifies the path to the yaml configuration file"
+        value_name = "FILE_OR_DIR",
+        help = "Specifies the path to the yaml configuration file, or a directory of yaml fragments to merge"
     )]
     config: PathBuf,
+
+    #[arg(
+        long,
+        help = "Stay resident and re-evaluate TTLs on filesystem events and a periodic sweep, instead of exiting after one pass"
+    )]
+    watch: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 60,
+        help = "Seconds between periodic sweeps while running with --watch"
+    )]
+    sweep_interval: u64,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = TimeSource::Created,
+        help = "Which timestamp drives TTL expiry; falls back to modified time when unsupported"
+    )]
+    time_source: TimeSource,
+
+    #[arg(
+        long,
+        help = "Log what would be deleted (with ttl, age and expiry delta) without touching the filesystem, then print a summary"
+    )]
+    dry_run: bool,
 }
 
-fn do_main(config: Config) -> anyhow::Result<()> {
-    let re = Regex::new(r"^ttl=(\d+)(min|d|m|y)$")?;
-    for path in config.paths_to_watch {
-        debug!("Processing path: {}", path.display());
-        let walker = WalkDir::new(path);
-        for entry in walker.into_iter() {
-            let entry = entry?;
-            if entry.file_type().is_dir() {
-                if let Err(e) = process_directory(&entry, &re) {
-                    error!(
-                        "Error processing directory {}: {}",
-                        entry.path().display(),
-                        e
-                    );
-                }
-            }
-        }
+fn do_main(cli: &Cli, config: Config) -> anyhow::Result<()> {
+    let config = config::expand_watch_paths(config)?;
+    let re = ttl::ttl_regex()?;
+    if cli.watch {
+        watch::run(
+            config,
+            re,
+            Duration::from_secs(cli.sweep_interval),
+            cli.time_source,
+            cli.dry_run,
+        )
+    } else {
+        watch::sweep_once(&config, &re, cli.time_source, cli.dry_run)
     }
-    Ok(())
 }
 
 fn main() {
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
+    let log_path = logging::init().expect("Failed to initialize logging");
+    debug!("Logging full session output to {}", log_path.display());
 
     let cli = Cli::parse();
 
-    // Read and parse the config file
-    let config_content = fs::read_to_string(&cli.config).expect("Failed to read config file");
-    let config: Config =
-        serde_yaml::from_str(&config_content).expect("Failed to parse config file");
+    let config = config::load(&cli.config).expect("Failed to load config");
 
     info!("Starting directory cleanup");
-    if let Err(e) = do_main(config) {
+    if let Err(e) = do_main(&cli, config) {
         error!("Error: {}", e);
         std::process::exit(1);
     }
@@ -120,6 +103,16 @@ mod tests {
         Ok(())
     }
 
+    fn test_cli() -> Cli {
+        Cli {
+            config: PathBuf::new(),
+            watch: false,
+            sweep_interval: 60,
+            time_source: TimeSource::Created,
+            dry_run: false,
+        }
+    }
+
     #[test]
     fn test_do_main_with_expired_directory() -> anyhow::Result<()> {
         let temp_dir = TempDir::new()?;
@@ -132,7 +125,7 @@ mod tests {
             paths_to_watch: vec![temp_dir.path().to_path_buf()],
         };
 
-        do_main(config)?;
+        do_main(&test_cli(), config)?;
 
         assert!(!temp_dir.path().join("ttl=1min").exists());
         Ok(())
@@ -147,7 +140,7 @@ mod tests {
             paths_to_watch: vec![temp_dir.path().to_path_buf()],
         };
 
-        do_main(config)?;
+        do_main(&test_cli(), config)?;
 
         assert!(temp_dir.path().join("ttl=1d").exists());
         Ok(())
@@ -162,7 +155,7 @@ mod tests {
             paths_to_watch: vec![temp_dir.path().to_path_buf()],
         };
 
-        do_main(config)?;
+        do_main(&test_cli(), config)?;
 
         assert!(temp_dir.path().join("regular_dir").exists());
         Ok(())
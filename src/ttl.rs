@@ -0,0 +1,301 @@
+use std::fs::{self, Metadata};
+use std::path::Path;
+use std::time::SystemTime;
+
+use anyhow::anyhow;
+use clap::ValueEnum;
+use regex::Regex;
+use tracing::{debug, info};
+
+use crate::ignore_tree::IgnoreTree;
+
+/// Which filesystem timestamp a directory's age is measured against.
+///
+/// `created()` is unsupported on many Linux filesystems/kernel configs and
+/// returns an error there, which otherwise silently aborts cleanup for an
+/// entire directory. [`reference_time`] falls back to a timestamp the
+/// platform does support instead of propagating that error.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TimeSource {
+    #[default]
+    Created,
+    Modified,
+    Accessed,
+}
+
+/// Resolves the timestamp to measure a directory's age from, preferring
+/// `preferred` but falling back through `created -> modified` (and
+/// `accessed -> modified`) when the platform doesn't support it.
+fn reference_time(metadata: &Metadata, preferred: TimeSource) -> anyhow::Result<SystemTime> {
+    resolve_reference_time(preferred, |source| match source {
+        TimeSource::Created => metadata.created(),
+        TimeSource::Modified => metadata.modified(),
+        TimeSource::Accessed => metadata.accessed(),
+    })
+}
+
+/// Fallback-selection logic behind [`reference_time`], factored out so it
+/// can be exercised without a real `Metadata` (e.g. to simulate a platform
+/// where `created()` isn't supported).
+fn resolve_reference_time(
+    preferred: TimeSource,
+    mut lookup: impl FnMut(TimeSource) -> std::io::Result<SystemTime>,
+) -> anyhow::Result<SystemTime> {
+    let fallbacks: &[TimeSource] = match preferred {
+        TimeSource::Created => &[TimeSource::Created, TimeSource::Modified],
+        TimeSource::Modified => &[TimeSource::Modified],
+        TimeSource::Accessed => &[TimeSource::Accessed, TimeSource::Modified],
+    };
+
+    let mut last_err = None;
+    for (i, source) in fallbacks.iter().enumerate() {
+        match lookup(*source) {
+            Ok(time) => {
+                if i > 0 {
+                    debug!("{preferred:?} timestamp unsupported, falling back to {source:?}");
+                }
+                return Ok(time);
+            }
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.expect("fallbacks is non-empty").into())
+}
+
+pub fn ttl_regex() -> anyhow::Result<Regex> {
+    Ok(Regex::new(r"^ttl=(\d+)(min|d|m|y)$")?)
+}
+
+/// Accumulates what a `--dry-run` pass would have deleted, so the caller
+/// can print one machine-readable line once the sweep finishes instead of
+/// only a log line per directory.
+#[derive(Debug, Default)]
+pub struct DryRunSummary {
+    pub directories: u64,
+    pub reclaimed_bytes: u64,
+}
+
+impl DryRunSummary {
+    fn record(&mut self, path: &Path) {
+        self.directories += 1;
+        self.reclaimed_bytes += directory_size(path);
+    }
+
+    pub fn log(&self) {
+        info!(
+            dry_run = true,
+            directories = self.directories,
+            reclaimed_bytes = self.reclaimed_bytes,
+            "Dry run complete: {} director{} would be deleted, reclaiming ~{} bytes",
+            self.directories,
+            if self.directories == 1 { "y" } else { "ies" },
+            self.reclaimed_bytes
+        );
+    }
+}
+
+/// Estimates a directory's on-disk size by summing the length of every
+/// regular file beneath it; best-effort, so unreadable entries are skipped.
+fn directory_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok().map(|m| (m, entry)))
+        .filter(|(metadata, _)| metadata.is_file())
+        .map(|(metadata, _)| metadata.len())
+        .sum()
+}
+
+pub fn process_directory(
+    entry: &walkdir::DirEntry,
+    re: &Regex,
+    time_source: TimeSource,
+    root: &Path,
+    ignores: &mut IgnoreTree,
+    dry_run: bool,
+    summary: &mut DryRunSummary,
+) -> anyhow::Result<()> {
+    let dir_name = entry
+        .path()
+        .file_name()
+        .ok_or(anyhow!("No file name"))?
+        .to_str()
+        .ok_or(anyhow!("No str"))?;
+
+    if let Some(captures) = re.captures(dir_name) {
+        if let (Some(ttl_value), Some(ttl_unit)) = (captures.get(1), captures.get(2)) {
+            let ttl_value = ttl_value.as_str().parse::<u64>()?;
+            let ttl_seconds = match ttl_unit.as_str() {
+                "min" => ttl_value * 60,
+                "d" => ttl_value * 24 * 60 * 60,
+                "m" => ttl_value * 30 * 24 * 60 * 60, // Approximate
+                "y" => ttl_value * 365 * 24 * 60 * 60, // Approximate
+                _ => return Ok(()),                   // Skip if unit is not recognized
+            };
+
+            let metadata = fs::metadata(entry.path())?;
+            let reference_time = reference_time(&metadata, time_source)?;
+            let current_time = SystemTime::now();
+
+            if let Ok(duration) = current_time.duration_since(reference_time) {
+                if duration.as_secs() > ttl_seconds {
+                    if ignores.is_ignored(root, entry.path()) {
+                        debug!(
+                            "Directory {} is expired but ignored, skipping",
+                            entry.path().display()
+                        );
+                        return Ok(());
+                    }
+                    if dry_run {
+                        info!(
+                            "Would delete directory: {} (ttl={}{}, age={}s, expired by {}s)",
+                            entry.path().display(),
+                            ttl_value,
+                            ttl_unit.as_str(),
+                            duration.as_secs(),
+                            duration.as_secs() - ttl_seconds,
+                        );
+                        summary.record(entry.path());
+                    } else {
+                        info!("Deleting directory: {}", entry.path().display());
+                        fs::remove_dir_all(entry.path())?;
+                    }
+                } else {
+                    debug!("Directory {} not yet expired", entry.path().display());
+                }
+            }
+        }
+    } else {
+        debug!("Directory {dir_name} does not match TTL pattern");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn unsupported() -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::Unsupported, "not supported")
+    }
+
+    #[test]
+    fn resolve_reference_time_uses_preferred_source_when_available() -> anyhow::Result<()> {
+        let modified = SystemTime::now();
+        let time = resolve_reference_time(TimeSource::Modified, |source| {
+            assert_eq!(source, TimeSource::Modified);
+            Ok(modified)
+        })?;
+        assert_eq!(time, modified);
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_reference_time_falls_back_from_created_to_modified() -> anyhow::Result<()> {
+        let modified = SystemTime::now();
+        let time = resolve_reference_time(TimeSource::Created, |source| match source {
+            TimeSource::Created => Err(unsupported()),
+            TimeSource::Modified => Ok(modified),
+            TimeSource::Accessed => unreachable!("accessed should not be consulted"),
+        })?;
+        assert_eq!(time, modified);
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_reference_time_falls_back_from_accessed_to_modified() -> anyhow::Result<()> {
+        let modified = SystemTime::now();
+        let time = resolve_reference_time(TimeSource::Accessed, |source| match source {
+            TimeSource::Accessed => Err(unsupported()),
+            TimeSource::Modified => Ok(modified),
+            TimeSource::Created => unreachable!("created should not be consulted"),
+        })?;
+        assert_eq!(time, modified);
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_reference_time_errors_when_every_source_is_unsupported() {
+        let result = resolve_reference_time(TimeSource::Created, |_| Err(unsupported()));
+        assert!(result.is_err());
+    }
+
+    fn scan_entry(path: &Path) -> walkdir::DirEntry {
+        walkdir::WalkDir::new(path)
+            .into_iter()
+            .next()
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn dry_run_records_expired_directory_without_deleting_it() -> anyhow::Result<()> {
+        let root = TempDir::new()?;
+        let expired = root.path().join("ttl=1min");
+        fs::create_dir(&expired)?;
+        fs::write(expired.join("payload"), [0u8; 42])?;
+
+        // Simulate passage of time
+        std::thread::sleep(std::time::Duration::from_secs(61));
+
+        let re = ttl_regex()?;
+        let mut ignores = IgnoreTree::new();
+        let mut summary = DryRunSummary::default();
+
+        process_directory(
+            &scan_entry(&expired),
+            &re,
+            TimeSource::Modified,
+            root.path(),
+            &mut ignores,
+            true,
+            &mut summary,
+        )?;
+
+        assert!(expired.exists());
+        assert_eq!(summary.directories, 1);
+        assert_eq!(summary.reclaimed_bytes, 42);
+        Ok(())
+    }
+
+    #[test]
+    fn non_dry_run_deletes_expired_directory() -> anyhow::Result<()> {
+        let root = TempDir::new()?;
+        let expired = root.path().join("ttl=1min");
+        fs::create_dir(&expired)?;
+
+        // Simulate passage of time
+        std::thread::sleep(std::time::Duration::from_secs(61));
+
+        let re = ttl_regex()?;
+        let mut ignores = IgnoreTree::new();
+        let mut summary = DryRunSummary::default();
+
+        process_directory(
+            &scan_entry(&expired),
+            &re,
+            TimeSource::Modified,
+            root.path(),
+            &mut ignores,
+            false,
+            &mut summary,
+        )?;
+
+        assert!(!expired.exists());
+        assert_eq!(summary.directories, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn directory_size_sums_nested_file_lengths() -> anyhow::Result<()> {
+        let root = TempDir::new()?;
+        fs::write(root.path().join("a"), [0u8; 10])?;
+        fs::create_dir(root.path().join("sub"))?;
+        fs::write(root.path().join("sub").join("b"), [0u8; 5])?;
+
+        assert_eq!(directory_size(root.path()), 15);
+        Ok(())
+    }
+}
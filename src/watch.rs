@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::{Duration, Instant};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use regex::Regex;
+use tracing::{debug, error, info, warn};
+
+use crate::{process_directory, Config, DryRunSummary, IgnoreTree, TimeSource};
+
+/// Minimum time between re-processing the same path, so a burst of events
+/// for the same directory (e.g. several files written before a rename)
+/// collapses into a single re-evaluation.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// The TTL matching knobs that stay constant for the life of a watch
+/// session, bundled so they don't have to be threaded through every
+/// function individually.
+struct ScanOptions<'a> {
+    re: &'a Regex,
+    time_source: TimeSource,
+    dry_run: bool,
+}
+
+/// Stays resident, re-running the full TTL sweep on a timer and
+/// re-evaluating a single directory whenever it is created or renamed
+/// under one of `config.paths_to_watch` (its subdirectories wait for the
+/// next sweep).
+pub fn run(
+    config: Config,
+    re: Regex,
+    sweep_interval: Duration,
+    time_source: TimeSource,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let opts = ScanOptions {
+        re: &re,
+        time_source,
+        dry_run,
+    };
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher: RecommendedWatcher = notify::recommended_watcher(tx)?;
+    for path in &config.paths_to_watch {
+        if let Err(e) = watcher.watch(path, RecursiveMode::Recursive) {
+            error!("Failed to watch {}: {}", path.display(), e);
+        }
+    }
+
+    let mut last_seen: HashMap<PathBuf, Instant> = HashMap::new();
+    let mut ignores = IgnoreTree::new();
+
+    info!(interval_secs = sweep_interval.as_secs(), "Entering watch mode");
+    sweep(&config, &opts, &mut ignores);
+    let mut next_sweep = Instant::now() + sweep_interval;
+
+    loop {
+        let timeout = next_sweep.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(timeout) {
+            Ok(Ok(event)) => {
+                if !is_relevant(&event.kind) {
+                    continue;
+                }
+                for path in event.paths {
+                    handle_event(path, &config, &opts, &mut last_seen, &mut ignores);
+                }
+            }
+            Ok(Err(e)) => warn!("Watch error: {}", e),
+            Err(RecvTimeoutError::Timeout) => {
+                debug!("Periodic sweep triggered");
+                evict_stale_debounce_entries(&mut last_seen);
+                sweep(&config, &opts, &mut ignores);
+                next_sweep = Instant::now() + sweep_interval;
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                warn!("Watch channel disconnected, exiting watch mode");
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Only create/rename events re-trigger a scan. This also takes care of
+/// ignoring the tool's own `remove_dir_all` calls without any extra
+/// bookkeeping: a delete only ever produces `Remove` events, which aren't
+/// relevant here, so our own deletions never reach `handle_event` in the
+/// first place.
+fn is_relevant(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_) | EventKind::Modify(notify::event::ModifyKind::Name(_))
+    )
+}
+
+fn handle_event(
+    path: PathBuf,
+    config: &Config,
+    opts: &ScanOptions,
+    last_seen: &mut HashMap<PathBuf, Instant>,
+    ignores: &mut IgnoreTree,
+) {
+    if last_seen
+        .get(&path)
+        .is_some_and(|last| last.elapsed() < DEBOUNCE)
+    {
+        return;
+    }
+    last_seen.insert(path.clone(), Instant::now());
+
+    let Some(root) = watched_root_for(&path, config) else {
+        return;
+    };
+    if !path.is_dir() {
+        return;
+    }
+
+    let entry = match walkdir::WalkDir::new(&path).max_depth(0).into_iter().next() {
+        Some(Ok(entry)) => entry,
+        _ => return,
+    };
+
+    let mut summary = DryRunSummary::default();
+    if let Err(e) = process_directory(
+        &entry,
+        opts.re,
+        opts.time_source,
+        root,
+        ignores,
+        opts.dry_run,
+        &mut summary,
+    ) {
+        error!("Error processing directory {}: {}", path.display(), e);
+    }
+    if opts.dry_run && summary.directories > 0 {
+        summary.log();
+    }
+}
+
+/// Drops `last_seen` entries older than [`DEBOUNCE`], called on each
+/// periodic sweep so the map doesn't grow without bound over a daemon's
+/// lifetime watching a high-churn tree where every event is for a directory
+/// with a unique, never-repeated name.
+fn evict_stale_debounce_entries(last_seen: &mut HashMap<PathBuf, Instant>) {
+    last_seen.retain(|_, last| last.elapsed() < DEBOUNCE);
+}
+
+fn watched_root_for<'a>(path: &Path, config: &'a Config) -> Option<&'a Path> {
+    config
+        .paths_to_watch
+        .iter()
+        .map(|root| root.as_path())
+        .find(|root| path.starts_with(root))
+}
+
+fn sweep(config: &Config, opts: &ScanOptions, ignores: &mut IgnoreTree) {
+    let mut summary = DryRunSummary::default();
+    for path in &config.paths_to_watch {
+        for entry in walk_dir_logging_errors(path) {
+            if let Err(e) = process_directory(
+                &entry,
+                opts.re,
+                opts.time_source,
+                path,
+                ignores,
+                opts.dry_run,
+                &mut summary,
+            ) {
+                error!(
+                    "Error processing directory {}: {}",
+                    entry.path().display(),
+                    e
+                );
+            }
+        }
+    }
+    if opts.dry_run {
+        summary.log();
+    }
+}
+
+/// Runs a one-off sweep outside of `--watch` mode: no events follow it, so
+/// there's no `last_seen` debounce state to maintain between directories.
+pub fn sweep_once(
+    config: &Config,
+    re: &Regex,
+    time_source: TimeSource,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let opts = ScanOptions {
+        re,
+        time_source,
+        dry_run,
+    };
+    let mut ignores = IgnoreTree::new();
+    let mut summary = DryRunSummary::default();
+    for path in &config.paths_to_watch {
+        debug!("Processing path: {}", path.display());
+        for entry in walk_dir_logging_errors(path) {
+            if let Err(e) = process_directory(
+                &entry,
+                opts.re,
+                opts.time_source,
+                path,
+                &mut ignores,
+                opts.dry_run,
+                &mut summary,
+            ) {
+                error!(
+                    "Error processing directory {}: {}",
+                    entry.path().display(),
+                    e
+                );
+            }
+        }
+    }
+    if dry_run {
+        summary.log();
+    }
+    Ok(())
+}
+
+/// Walks `path`, logging and skipping any entry `WalkDir` fails to read
+/// (e.g. a permission-denied subdirectory) instead of aborting the whole
+/// walk, and filtering down to directories since that's all every caller
+/// wants.
+fn walk_dir_logging_errors(path: &Path) -> impl Iterator<Item = walkdir::DirEntry> + '_ {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(move |entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                error!("Error walking {}: {}", path.display(), e);
+                None
+            }
+        })
+        .filter(|entry| entry.file_type().is_dir())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_relevant_accepts_create_and_rename_events() {
+        assert!(is_relevant(&EventKind::Create(
+            notify::event::CreateKind::Folder
+        )));
+        assert!(is_relevant(&EventKind::Modify(
+            notify::event::ModifyKind::Name(notify::event::RenameMode::To)
+        )));
+    }
+
+    #[test]
+    fn is_relevant_rejects_other_event_kinds() {
+        assert!(!is_relevant(&EventKind::Remove(
+            notify::event::RemoveKind::Folder
+        )));
+        assert!(!is_relevant(&EventKind::Access(
+            notify::event::AccessKind::Read
+        )));
+    }
+
+    #[test]
+    fn watched_root_for_finds_containing_root() {
+        let config = Config {
+            paths_to_watch: vec![PathBuf::from("/build/a"), PathBuf::from("/build/b")],
+        };
+
+        assert_eq!(
+            watched_root_for(Path::new("/build/b/sub/dir"), &config),
+            Some(Path::new("/build/b"))
+        );
+        assert_eq!(watched_root_for(Path::new("/other"), &config), None);
+    }
+
+    #[test]
+    fn debounce_suppresses_events_within_window_but_not_after() {
+        let mut last_seen: HashMap<PathBuf, Instant> = HashMap::new();
+        let path = PathBuf::from("/build/a/sub");
+
+        assert!(last_seen
+            .get(&path)
+            .is_none_or(|last| last.elapsed() >= DEBOUNCE));
+        last_seen.insert(path.clone(), Instant::now());
+
+        assert!(last_seen
+            .get(&path)
+            .is_some_and(|last| last.elapsed() < DEBOUNCE));
+
+        last_seen.insert(path.clone(), Instant::now() - DEBOUNCE - Duration::from_millis(1));
+        assert!(last_seen
+            .get(&path)
+            .is_none_or(|last| last.elapsed() >= DEBOUNCE));
+    }
+
+    #[test]
+    fn evict_stale_debounce_entries_drops_expired_but_keeps_fresh() {
+        let mut last_seen: HashMap<PathBuf, Instant> = HashMap::new();
+        last_seen.insert(
+            PathBuf::from("/build/stale"),
+            Instant::now() - DEBOUNCE - Duration::from_millis(1),
+        );
+        last_seen.insert(PathBuf::from("/build/fresh"), Instant::now());
+
+        evict_stale_debounce_entries(&mut last_seen);
+
+        assert_eq!(last_seen.len(), 1);
+        assert!(last_seen.contains_key(Path::new("/build/fresh")));
+    }
+}